@@ -6,7 +6,7 @@ use librad::{
     profile::Profile,
 };
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     convert::{TryFrom, TryInto},
     str::FromStr,
     io::Write,
@@ -31,6 +31,8 @@ enum Command {
     List(List),
     ChangeGraph(ChangeGraph),
     AutomergeDoc(AutomergeDoc),
+    Format(Format),
+    InsertText(InsertText),
 }
 
 /// Create a new issue
@@ -89,7 +91,170 @@ struct AutomergeDoc {
     issue_id: ObjectId,
 }
 
-const SCHEMA_JSON_BYTES: &[u8; 702] = std::include_bytes!("./schema.json");
+/// Apply a formatting mark to a range of a rich-text field
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "format")]
+struct Format {
+    /// the ID of the issue
+    #[argh(option)]
+    issue_id: ObjectId,
+    /// the rich-text field to annotate, e.g. "description" or "comments.0.comment"
+    #[argh(option)]
+    path: String,
+    /// the start of the marked range (inclusive, in characters)
+    #[argh(option)]
+    start: u32,
+    /// the end of the marked range (exclusive, in characters)
+    #[argh(option)]
+    end: u32,
+    /// the kind of mark to apply: bold, italic or link
+    #[argh(option)]
+    mark: MarkKind,
+    /// the value associated with the mark, e.g. the link target
+    #[argh(option)]
+    value: Option<String>,
+}
+
+/// Insert text into a rich-text field, growing any boundary marks per their expand policy
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "insert-text")]
+struct InsertText {
+    /// the ID of the issue
+    #[argh(option)]
+    issue_id: ObjectId,
+    /// the rich-text field to edit, e.g. "description" or "comments.0.comment"
+    #[argh(option)]
+    path: String,
+    /// the character offset at which to insert
+    #[argh(option)]
+    at: u32,
+    /// the text to insert
+    #[argh(option)]
+    text: String,
+}
+
+/// the kind of formatting mark applied to a span of text
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkKind {
+    Bold,
+    Italic,
+    Link,
+}
+
+impl MarkKind {
+    /// the expand policy used when text is typed at the boundary of a mark of this kind
+    fn default_expand(&self) -> ExpandPolicy {
+        match self {
+            MarkKind::Bold | MarkKind::Italic => ExpandPolicy::Both,
+            MarkKind::Link => ExpandPolicy::None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            MarkKind::Bold => "bold",
+            MarkKind::Italic => "italic",
+            MarkKind::Link => "link",
+        }
+    }
+}
+
+impl FromStr for MarkKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bold" => Ok(MarkKind::Bold),
+            "italic" => Ok(MarkKind::Italic),
+            "link" => Ok(MarkKind::Link),
+            other => Err(format!(
+                "unknown mark kind '{}', expected one of bold, italic, link",
+                other
+            )),
+        }
+    }
+}
+
+/// controls whether text typed at a mark's boundary inherits the mark
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpandPolicy {
+    None,
+    Before,
+    After,
+    Both,
+}
+
+impl ExpandPolicy {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ExpandPolicy::None => "none",
+            ExpandPolicy::Before => "before",
+            ExpandPolicy::After => "after",
+            ExpandPolicy::Both => "both",
+        }
+    }
+
+    /// whether text inserted immediately before this mark's start should join it
+    fn expands_before(&self) -> bool {
+        matches!(self, ExpandPolicy::Before | ExpandPolicy::Both)
+    }
+
+    /// whether text inserted immediately after this mark's end should join it
+    fn expands_after(&self) -> bool {
+        matches!(self, ExpandPolicy::After | ExpandPolicy::Both)
+    }
+}
+
+impl FromStr for ExpandPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(ExpandPolicy::None),
+            "before" => Ok(ExpandPolicy::Before),
+            "after" => Ok(ExpandPolicy::After),
+            "both" => Ok(ExpandPolicy::Both),
+            other => Err(format!(
+                "unknown expand policy '{}', expected one of none, before, after, both",
+                other
+            )),
+        }
+    }
+}
+
+/// Compute a mark's new `[start, end)` bounds after inserting `inserted_len` characters at `at`,
+/// per the mark's expand policy for text typed exactly at one of its boundaries
+fn adjust_mark_bounds(start: u32, end: u32, expand: ExpandPolicy, at: u32, inserted_len: u32) -> (u32, u32) {
+    if at < start {
+        (start + inserted_len, end + inserted_len)
+    } else if at > end {
+        (start, end)
+    } else if at == start && at == end {
+        if expand.expands_before() || expand.expands_after() {
+            (start, end + inserted_len)
+        } else {
+            (start + inserted_len, end + inserted_len)
+        }
+    } else if at == start {
+        if expand.expands_before() {
+            (start, end + inserted_len)
+        } else {
+            (start + inserted_len, end + inserted_len)
+        }
+    } else if at == end {
+        if expand.expands_after() {
+            (start, end + inserted_len)
+        } else {
+            (start, end)
+        }
+    } else {
+        (start, end + inserted_len)
+    }
+}
+
+const SCHEMA_JSON_BYTES: &[u8; 1695] = std::include_bytes!("./schema.json");
 
 lazy_static! {
     static ref TYPENAME: TypeName = FromStr::from_str("xyz.example.radicle.issue").unwrap();
@@ -137,12 +302,10 @@ fn main() {
                 .retrieve(&args.project_urn, &TYPENAME, &issue_id)
                 .unwrap();
             if let Some(object) = object {
-                match evaluate_history(object.history()) {
-                    Ok((mut frontend, _backend)) => {
-                        println!(
-                            "{}",
-                            serde_json::to_string(&frontend.state().to_json()).unwrap()
-                        );
+                let issue: Result<Issue, _> = object.history().try_into();
+                match issue {
+                    Ok(issue) => {
+                        println!("{}", serde_json::to_string(&issue.view()).unwrap());
                     },
                     Err(e) => {
                         eprintln!("error evaluating {}", e);
@@ -181,7 +344,7 @@ fn main() {
                         let author = local_id.urn();
                         let mut comment_map = HashMap::new();
                         comment_map.insert("author".into(), automerge::Value::Primitive(automerge::Primitive::Str(author.to_string().into())));
-                        comment_map.insert("comment".into(), automerge::Value::Primitive(automerge::Primitive::Str(comment.into())));
+                        comment_map.insert("comment".into(), rich_text_value(&comment));
                         let new_comment = automerge::Value::Map(comment_map);
                             
                         //let new_comment = serde_json::json!({
@@ -263,22 +426,250 @@ fn main() {
                 println!("No object found");
             }
         }
+        Command::Format(Format { issue_id, path, start, end, mark, value }) => {
+            let store = storage.collaborative_objects(Some(paths.cob_cache_dir().to_path_buf()));
+            let object = store
+                .retrieve(&args.project_urn, &TYPENAME, &issue_id)
+                .unwrap();
+            if let Some(object) = object {
+                let (mut frontend, mut backend) = match evaluate_history(object.history()) {
+                    Ok(it) => it,
+                    Err(e) => {
+                        eprintln!("error loading issue: {}", e);
+                        return;
+                    }
+                };
+                frontend.apply_patch(backend.get_patch().unwrap()).unwrap();
+                let field_path = match resolve_rich_text_path(&path) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                };
+                if start >= end {
+                    eprintln!("invalid range: start ({}) must be less than end ({})", start, end);
+                    return;
+                }
+                let expand = mark.default_expand();
+                let (_, change) = frontend
+                    .change(Some("Add a format mark".to_string()), |d| {
+                        let text_path = field_path.clone().key("text");
+                        let text_len = if let Some(automerge::Value::Text(chars)) = d.value_at_path(&text_path) {
+                            chars.len() as u32
+                        } else {
+                            eprintln!("no such rich-text field '{}'", path);
+                            return Ok(());
+                        };
+                        if end > text_len {
+                            eprintln!(
+                                "invalid range: end ({}) is past the end of the text (length {})",
+                                end, text_len
+                            );
+                            return Ok(());
+                        }
+                        let marks_path = field_path.clone().key("marks");
+                        let num_marks =
+                            if let Some(automerge::Value::List(marks)) = d.value_at_path(&marks_path) {
+                                marks.len() as u32
+                            } else {
+                                eprintln!("no such rich-text field '{}'", path);
+                                return Ok(());
+                            };
+                        // Recorded so `RichText::spans` only replays edits that happened after
+                        // this mark was created when recomputing its effective bounds.
+                        let since_edit = if let Some(automerge::Value::List(edits)) =
+                            d.value_at_path(&field_path.key("edits"))
+                        {
+                            edits.len() as u32
+                        } else {
+                            eprintln!("no such rich-text field '{}'", path);
+                            return Ok(());
+                        };
+                        let mut mark_map = HashMap::new();
+                        mark_map.insert("start".into(), automerge::Value::Primitive(automerge::Primitive::Uint(start as u64)));
+                        mark_map.insert("end".into(), automerge::Value::Primitive(automerge::Primitive::Uint(end as u64)));
+                        mark_map.insert("mark".into(), automerge::Value::Primitive(automerge::Primitive::Str(mark.as_str().into())));
+                        mark_map.insert("expand".into(), automerge::Value::Primitive(automerge::Primitive::Str(expand.as_str().into())));
+                        mark_map.insert("since_edit".into(), automerge::Value::Primitive(automerge::Primitive::Uint(since_edit as u64)));
+                        mark_map.insert(
+                            "value".into(),
+                            match &value {
+                                Some(v) => automerge::Value::Primitive(automerge::Primitive::Str(v.clone().into())),
+                                None => automerge::Value::Primitive(automerge::Primitive::Null),
+                            },
+                        );
+                        d.add_change(automerge::LocalChange::insert(
+                            marks_path.index(num_marks),
+                            automerge::Value::Map(mark_map),
+                        ))
+                    })
+                    .unwrap();
+                let change = match change {
+                    Some(c) => c,
+                    None => {
+                        eprintln!("failed to apply mark: '{}' is not a valid rich-text field", path);
+                        return;
+                    }
+                };
+                let change: automerge::Change = change.into();
+                backend.apply_changes(vec![change.clone()]).unwrap();
+                let contents = librad::collaborative_objects::EntryContents::Automerge(change.raw_bytes().to_vec());
+                store
+                    .update(
+                        &local_id,
+                        &args.project_urn,
+                        librad::collaborative_objects::UpdateObjectSpec{
+                            typename: TYPENAME.clone(),
+                            object_id: issue_id,
+                            changes: contents,
+                            message: Some("format text".to_string()),
+                        }
+                    )
+                    .unwrap();
+                println!("Update complete");
+            } else {
+                println!("No object found");
+            }
+        }
+        Command::InsertText(InsertText { issue_id, path, at, text }) => {
+            let store = storage.collaborative_objects(Some(paths.cob_cache_dir().to_path_buf()));
+            let object = store
+                .retrieve(&args.project_urn, &TYPENAME, &issue_id)
+                .unwrap();
+            if let Some(object) = object {
+                let (mut frontend, mut backend) = match evaluate_history(object.history()) {
+                    Ok(it) => it,
+                    Err(e) => {
+                        eprintln!("error loading issue: {}", e);
+                        return;
+                    }
+                };
+                frontend.apply_patch(backend.get_patch().unwrap()).unwrap();
+                let field_path = match resolve_rich_text_path(&path) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                };
+                let inserted_len = text.chars().count() as u32;
+                let (_, change) = frontend
+                    .change(Some("Insert text".to_string()), |d| {
+                        let text_path = field_path.clone().key("text");
+                        let text_len = if let Some(automerge::Value::Text(chars)) = d.value_at_path(&text_path) {
+                            chars.len() as u32
+                        } else {
+                            eprintln!("no such rich-text field '{}'", path);
+                            return Ok(());
+                        };
+                        if at > text_len {
+                            eprintln!(
+                                "insertion point {} is past the end of the text (length {})",
+                                at, text_len
+                            );
+                            return Ok(());
+                        }
+                        let edits_path = field_path.key("edits");
+                        let num_edits =
+                            if let Some(automerge::Value::List(edits)) = d.value_at_path(&edits_path) {
+                                edits.len() as u32
+                            } else {
+                                eprintln!("no such rich-text field '{}'", path);
+                                return Ok(());
+                            };
+                        for (i, ch) in text.chars().enumerate() {
+                            d.add_change(automerge::LocalChange::insert(
+                                text_path.clone().index(at + i as u32),
+                                automerge::Value::Primitive(automerge::Primitive::Str(ch.to_string().into())),
+                            ))?;
+                        }
+                        // Record the edit rather than rewriting each mark's start/end in place:
+                        // two peers editing concurrently both append here (an Automerge list
+                        // merges concurrent inserts without conflict), whereas writing adjusted
+                        // bounds directly would make start/end a pair of per-key LWW registers
+                        // and silently drop one side. `RichText::spans` replays this log to
+                        // recompute each mark's effective bounds.
+                        let mut edit_map = HashMap::new();
+                        edit_map.insert("at".into(), automerge::Value::Primitive(automerge::Primitive::Uint(at as u64)));
+                        edit_map.insert("len".into(), automerge::Value::Primitive(automerge::Primitive::Uint(inserted_len as u64)));
+                        d.add_change(automerge::LocalChange::insert(
+                            edits_path.index(num_edits),
+                            automerge::Value::Map(edit_map),
+                        ))
+                    })
+                    .unwrap();
+                let change = match change {
+                    Some(c) => c,
+                    None => {
+                        eprintln!("failed to insert text: '{}' is not a valid rich-text field", path);
+                        return;
+                    }
+                };
+                let change: automerge::Change = change.into();
+                backend.apply_changes(vec![change.clone()]).unwrap();
+                let contents = librad::collaborative_objects::EntryContents::Automerge(change.raw_bytes().to_vec());
+                store
+                    .update(
+                        &local_id,
+                        &args.project_urn,
+                        librad::collaborative_objects::UpdateObjectSpec{
+                            typename: TYPENAME.clone(),
+                            object_id: issue_id,
+                            changes: contents,
+                            message: Some("insert text".to_string()),
+                        }
+                    )
+                    .unwrap();
+                println!("Update complete");
+            } else {
+                println!("No object found");
+            }
+        }
     }
 }
 
+/// Build the path to a rich-text field (e.g. `description`, or `comments.3.comment`)
+fn resolve_rich_text_path(path: &str) -> Result<automerge::Path, String> {
+    let segments: Vec<&str> = path.split('.').collect();
+    match segments.as_slice() {
+        ["description"] => Ok(automerge::Path::root().key("description")),
+        ["comments", index, "comment"] => {
+            let index: u32 = index
+                .parse()
+                .map_err(|_| format!("invalid comment index '{}'", index))?;
+            Ok(automerge::Path::root().key("comments").index(index).key("comment"))
+        }
+        _ => Err(format!(
+            "unsupported path '{}', expected \"description\" or \"comments.<index>.comment\"",
+            path
+        )),
+    }
+}
+
+/// Build the automerge value for a rich-text field: a Text sequence, an empty mark list and an
+/// empty edit log (see `RichText` for why edits are logged rather than folded into marks in
+/// place)
+fn rich_text_value(s: &str) -> automerge::Value {
+    let mut map = HashMap::new();
+    map.insert("text".into(), automerge::Value::Text(s.chars().collect()));
+    map.insert("marks".into(), automerge::Value::List(vec![]));
+    map.insert("edits".into(), automerge::Value::List(vec![]));
+    automerge::Value::Map(map)
+}
+
 fn initial_doc(author: Urn, title: String, description: String) -> librad::collaborative_objects::EntryContents {
     let mut frontend = automerge::Frontend::new();
     let (_, change) = frontend
         .change::<_, (), automerge::InvalidChangeRequest>(Some("create issue".to_string()), |d| {
-            let init = serde_json::json!({
-                "title": title,
-                "description": description,
-                "author": author.to_string(),
-                "comments": [],
-            });
+            let mut doc = HashMap::new();
+            doc.insert("title".into(), automerge::Value::Primitive(automerge::Primitive::Str(title.into())));
+            doc.insert("description".into(), rich_text_value(&description));
+            doc.insert("author".into(), automerge::Value::Primitive(automerge::Primitive::Str(author.to_string().into())));
+            doc.insert("comments".into(), automerge::Value::List(vec![]));
             d.add_change(automerge::LocalChange::set(
                 automerge::Path::root(),
-                automerge::Value::from_json(&init),
+                automerge::Value::Map(doc),
             ))?;
             Ok(())
         })
@@ -291,17 +682,166 @@ fn initial_doc(author: Urn, title: String, description: String) -> librad::colla
 #[derive(serde::Deserialize)]
 pub struct Issue {
     pub title: String,
-    pub description: String,
+    pub description: RichText,
     pub comments: Vec<Comment>,
     pub author: String,
 }
 
 #[derive(serde::Deserialize)]
 pub struct Comment {
-    pub comment: String,
+    pub comment: RichText,
     pub author: String,
 }
 
+impl Issue {
+    /// Render the issue with each rich-text field expanded into its constituent spans
+    pub fn view(&self) -> IssueView {
+        IssueView {
+            title: self.title.clone(),
+            author: self.author.clone(),
+            description: self.description.spans(),
+            comments: self
+                .comments
+                .iter()
+                .map(|c| CommentView {
+                    author: c.author.clone(),
+                    comment: c.comment.spans(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A rich-text field: an Automerge Text sequence, the marks applied to it, and the log of
+/// `insert-text` edits applied since the field was created.
+///
+/// Mark bounds are *not* kept up to date in place: two peers running `insert-text` concurrently
+/// would each compute adjusted bounds from their own pre-merge view and write them as a plain
+/// `set` on the mark's `start`/`end` keys, which Automerge resolves as last-writer-wins registers
+/// — one side's adjustment would be silently dropped. Instead each `insert-text` call only
+/// *appends* an edit record, which Automerge's list CRDT merges without conflict (both peers'
+/// edits survive, in a deterministic merged order), and `spans` recomputes each mark's effective
+/// bounds by replaying the edit log. This is a partial fix, not a full anchor-based CRDT mark: the
+/// replay assumes edits are applied in the document's merged list order, so a mark created
+/// concurrently with (rather than strictly before or after) an edit near its boundary can still
+/// end up with bounds that don't match either peer's intent. A fully correct implementation would
+/// anchor marks to stable list-element identities instead of raw integer offsets.
+#[derive(serde::Deserialize)]
+pub struct RichText {
+    pub text: String,
+    pub marks: Vec<Mark>,
+    pub edits: Vec<Edit>,
+}
+
+impl RichText {
+    /// Walk the text as contiguous spans, each sharing the same set of active marks
+    pub fn spans(&self) -> Vec<Span> {
+        let chars: Vec<char> = self.text.chars().collect();
+        let len = chars.len() as u32;
+
+        let effective: Vec<(u32, u32, &Mark)> = self
+            .marks
+            .iter()
+            .map(|m| {
+                let (start, end) = self.effective_bounds(m);
+                (start.min(len), end.min(len), m)
+            })
+            .collect();
+
+        let mut boundaries: BTreeSet<u32> = BTreeSet::new();
+        boundaries.insert(0);
+        boundaries.insert(len);
+        for (start, end, _) in &effective {
+            boundaries.insert(*start);
+            boundaries.insert(*end);
+        }
+        let boundaries: Vec<u32> = boundaries.into_iter().collect();
+
+        let mut spans: Vec<Span> = Vec::new();
+        for pair in boundaries.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            if start >= end {
+                continue;
+            }
+            let active: Vec<ActiveMark> = effective
+                .iter()
+                .filter(|(mark_start, mark_end, _)| *mark_start <= start && end <= *mark_end)
+                .map(|(_, _, m)| ActiveMark { mark: m.mark, value: m.value.clone() })
+                .collect();
+            let text: String = chars[start as usize..end as usize].iter().collect();
+
+            match spans.last_mut() {
+                Some(prev) if prev.marks == active => {
+                    prev.end = end;
+                    prev.text.push_str(&text);
+                }
+                _ => spans.push(Span { start, end, text, marks: active }),
+            }
+        }
+        spans
+    }
+
+    /// Recompute a mark's `[start, end)` by replaying the edits recorded since it was created
+    fn effective_bounds(&self, mark: &Mark) -> (u32, u32) {
+        let mut start = mark.start;
+        let mut end = mark.end;
+        for edit in self.edits.iter().skip(mark.since_edit as usize) {
+            let (new_start, new_end) = adjust_mark_bounds(start, end, mark.expand, edit.at, edit.len);
+            start = new_start;
+            end = new_end;
+        }
+        (start, end)
+    }
+}
+
+/// A mark applied to a `[start, end)` range of a rich-text field, as of `since_edit` entries into
+/// the field's edit log (see `RichText`'s doc comment)
+#[derive(serde::Deserialize, Clone)]
+pub struct Mark {
+    pub start: u32,
+    pub end: u32,
+    pub mark: MarkKind,
+    pub value: Option<String>,
+    pub expand: ExpandPolicy,
+    pub since_edit: u32,
+}
+
+/// A single `insert-text` edit recorded against a rich-text field's edit log
+#[derive(serde::Deserialize, Clone)]
+pub struct Edit {
+    pub at: u32,
+    pub len: u32,
+}
+
+/// A maximal run of characters sharing the same active mark set
+#[derive(serde::Serialize)]
+pub struct Span {
+    pub start: u32,
+    pub end: u32,
+    pub text: String,
+    pub marks: Vec<ActiveMark>,
+}
+
+#[derive(serde::Serialize, Clone, PartialEq)]
+pub struct ActiveMark {
+    pub mark: MarkKind,
+    pub value: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct IssueView {
+    pub title: String,
+    pub author: String,
+    pub description: Vec<Span>,
+    pub comments: Vec<CommentView>,
+}
+
+#[derive(serde::Serialize)]
+pub struct CommentView {
+    pub author: String,
+    pub comment: Vec<Span>,
+}
+
 impl TryFrom<&History> for Issue {
     type Error = anyhow::Error;
 
@@ -332,3 +872,125 @@ fn evaluate_history(history: &librad::collaborative_objects::History) -> anyhow:
     frontend.apply_patch(backend.get_patch()?)?;
     Ok((frontend, backend))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adjust_mark_bounds_shifts_marks_entirely_after_the_insertion_point() {
+        assert_eq!(adjust_mark_bounds(5, 10, ExpandPolicy::None, 2, 3), (8, 13));
+    }
+
+    #[test]
+    fn adjust_mark_bounds_leaves_marks_entirely_before_the_insertion_point_untouched() {
+        assert_eq!(adjust_mark_bounds(5, 10, ExpandPolicy::Both, 12, 3), (5, 10));
+    }
+
+    #[test]
+    fn adjust_mark_bounds_always_grows_around_interior_insertions() {
+        // an insertion strictly inside the mark is absorbed regardless of expand policy
+        assert_eq!(adjust_mark_bounds(5, 10, ExpandPolicy::None, 7, 3), (5, 13));
+    }
+
+    #[test]
+    fn adjust_mark_bounds_start_boundary_honors_expand_before() {
+        assert_eq!(adjust_mark_bounds(5, 10, ExpandPolicy::Before, 5, 3), (5, 13));
+        assert_eq!(adjust_mark_bounds(5, 10, ExpandPolicy::After, 5, 3), (8, 13));
+    }
+
+    #[test]
+    fn adjust_mark_bounds_end_boundary_honors_expand_after() {
+        assert_eq!(adjust_mark_bounds(5, 10, ExpandPolicy::After, 10, 3), (5, 13));
+        assert_eq!(adjust_mark_bounds(5, 10, ExpandPolicy::Before, 10, 3), (5, 10));
+    }
+
+    #[test]
+    fn adjust_mark_bounds_zero_width_mark_follows_expand_policy() {
+        assert_eq!(adjust_mark_bounds(5, 5, ExpandPolicy::None, 5, 3), (8, 8));
+        assert_eq!(adjust_mark_bounds(5, 5, ExpandPolicy::Both, 5, 3), (5, 8));
+    }
+
+    fn mark(start: u32, end: u32, kind: MarkKind) -> Mark {
+        Mark { start, end, mark: kind, value: None, expand: ExpandPolicy::None, since_edit: 0 }
+    }
+
+    #[test]
+    fn spans_splits_overlapping_marks_into_their_constituent_runs() {
+        let rt = RichText {
+            text: "hello!".to_string(),
+            marks: vec![mark(0, 4, MarkKind::Bold), mark(2, 6, MarkKind::Italic)],
+            edits: vec![],
+        };
+        let rendered: Vec<(u32, u32, String, Vec<MarkKind>)> = rt
+            .spans()
+            .iter()
+            .map(|s| (s.start, s.end, s.text.clone(), s.marks.iter().map(|m| m.mark).collect()))
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                (0, 2, "he".to_string(), vec![MarkKind::Bold]),
+                (2, 4, "ll".to_string(), vec![MarkKind::Bold, MarkKind::Italic]),
+                (4, 6, "o!".to_string(), vec![MarkKind::Italic]),
+            ]
+        );
+    }
+
+    #[test]
+    fn spans_merges_adjacent_runs_that_share_the_same_active_marks() {
+        let rt = RichText {
+            text: "abcdef".to_string(),
+            marks: vec![mark(0, 3, MarkKind::Bold), mark(3, 6, MarkKind::Bold)],
+            edits: vec![],
+        };
+        let spans = rt.spans();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].text, "abcdef");
+    }
+
+    #[test]
+    fn spans_handles_a_mark_nested_entirely_within_another() {
+        let rt = RichText {
+            text: "0123456789".to_string(),
+            marks: vec![mark(0, 10, MarkKind::Bold), mark(3, 6, MarkKind::Italic)],
+            edits: vec![],
+        };
+        let rendered: Vec<(String, usize)> =
+            rt.spans().iter().map(|s| (s.text.clone(), s.marks.len())).collect();
+        assert_eq!(
+            rendered,
+            vec![
+                ("012".to_string(), 1),
+                ("345".to_string(), 2),
+                ("6789".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn effective_bounds_incorporates_both_concurrent_edits_regardless_of_merge_order() {
+        // Two peers concurrently run `insert-text` before a mark's start. Automerge's list CRDT
+        // may resolve the two appended edit records in either order, but folding them should
+        // incorporate both peers' inserted lengths either way -- unlike the old scheme of
+        // overwriting the mark's start/end as a last-writer-wins `set`, which would have kept
+        // only one peer's shift and silently dropped the other's.
+        let the_mark = mark(5, 10, MarkKind::Bold);
+        let peer_a = Edit { at: 0, len: 2 };
+        let peer_b = Edit { at: 1, len: 4 };
+
+        let a_then_b = RichText {
+            text: String::new(),
+            marks: vec![the_mark.clone()],
+            edits: vec![peer_a.clone(), peer_b.clone()],
+        };
+        let b_then_a = RichText {
+            text: String::new(),
+            marks: vec![the_mark.clone()],
+            edits: vec![peer_b, peer_a],
+        };
+
+        assert_eq!(a_then_b.effective_bounds(&the_mark), (11, 16));
+        assert_eq!(b_then_a.effective_bounds(&the_mark), (11, 16));
+    }
+}